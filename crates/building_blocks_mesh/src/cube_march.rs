@@ -0,0 +1,166 @@
+//! The cube-marching core shared by `marching_cubes` and `material_mesh`: classifying a cell's 8
+//! corners against the isosurface, interpolating the intersected edges, and walking the triangle
+//! table. Each caller supplies its own corner sampling (a distance paired with whatever per-voxel
+//! attribute it wants carried along, e.g. a material ID, or `()` if there is none) and owns what
+//! it does with the resulting triangles (normals, vertex attributes, etc).
+
+use crate::marching_cubes_tables::{EDGE_TABLE, TRI_TABLE};
+
+use building_blocks_core::prelude::*;
+
+pub(crate) const ISO_LEVEL: f32 = 0.0;
+
+/// The corner offsets of a unit cube, in the same order used to index `EDGE_TABLE` and
+/// `TRI_TABLE`.
+pub(crate) const CUBE_CORNERS: [Point3i; 8] = [
+    PointN([0, 0, 0]),
+    PointN([1, 0, 0]),
+    PointN([1, 1, 0]),
+    PointN([0, 1, 0]),
+    PointN([0, 0, 1]),
+    PointN([1, 0, 1]),
+    PointN([1, 1, 1]),
+    PointN([0, 1, 1]),
+];
+
+/// The two corner indices (into `CUBE_CORNERS`) that each of the 12 cube edges connects.
+pub(crate) const EDGE_CORNERS: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 0),
+    (4, 5),
+    (5, 6),
+    (6, 7),
+    (7, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// A triangle vertex produced by marching an edge: its interpolated position, which edge (into
+/// `EDGE_CORNERS`) it came from, and the `t` it was interpolated at, so a caller that needs more
+/// than position (e.g. a blended normal) doesn't have to re-derive `t` from scratch.
+#[derive(Clone, Copy)]
+pub(crate) struct MarchedVertex {
+    pub position: Point3f,
+    pub edge: usize,
+    pub t: f32,
+}
+
+/// The result of marching a single cell: the corner sign bitmask (useful for deriving per-cell
+/// attributes, like which corner contributed a material), the raw corner samples (useful for
+/// building a normal or picking a material without re-querying the source array), and the
+/// interpolated triangles.
+pub(crate) struct MarchedCell<T> {
+    pub cube_index: usize,
+    pub corner_values: [f32; 8],
+    pub corner_attrs: [T; 8],
+    pub triangles: Vec<[MarchedVertex; 3]>,
+}
+
+/// Classifies the 8 corners of the cell at `p` against `ISO_LEVEL` using `sample` (which returns
+/// a distance paired with whatever attribute the caller wants to track per corner), then
+/// interpolates the edges and triangles indicated by the lookup tables. Returns `None` if the
+/// cell is entirely inside or outside the surface.
+pub(crate) fn march_cube<T>(
+    p: &Point3i,
+    sample: impl Fn(Point3i) -> (f32, T),
+) -> Option<MarchedCell<T>> {
+    let mut corner_values = [0.0; 8];
+    let mut corner_attrs = Vec::with_capacity(8);
+    let mut cube_index = 0;
+    for (i, offset) in CUBE_CORNERS.iter().enumerate() {
+        let (d, attr) = sample(*p + *offset);
+        corner_values[i] = d;
+        corner_attrs.push(attr);
+        if d < ISO_LEVEL {
+            cube_index |= 1 << i;
+        }
+    }
+    let corner_attrs: [T; 8] = match corner_attrs.try_into() {
+        Ok(a) => a,
+        Err(_) => unreachable!("exactly 8 corners were sampled"),
+    };
+
+    let edge_mask = EDGE_TABLE[cube_index];
+    if edge_mask == 0 {
+        return None;
+    }
+
+    let mut edge_vertices: [Option<MarchedVertex>; 12] = [None; 12];
+    for (edge, (c0, c1)) in EDGE_CORNERS.iter().enumerate() {
+        if edge_mask & (1 << edge) == 0 {
+            continue;
+        }
+
+        let p0: Point3f = (*p + CUBE_CORNERS[*c0]).into();
+        let p1: Point3f = (*p + CUBE_CORNERS[*c1]).into();
+        let (d0, d1) = (corner_values[*c0], corner_values[*c1]);
+        let t = (ISO_LEVEL - d0) / (d1 - d0);
+
+        edge_vertices[edge] = Some(MarchedVertex {
+            position: p0 + (p1 - p0).scalar_mul(t),
+            edge,
+            t,
+        });
+    }
+
+    let mut triangles = Vec::new();
+    for tri in TRI_TABLE[cube_index].chunks(3) {
+        if tri[0] < 0 {
+            break;
+        }
+
+        triangles.push([
+            edge_vertices[tri[0] as usize].unwrap(),
+            edge_vertices[tri[1] as usize].unwrap(),
+            edge_vertices[tri[2] as usize].unwrap(),
+        ]);
+    }
+
+    Some(MarchedCell {
+        cube_index,
+        corner_values,
+        corner_attrs,
+        triangles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_entirely_outside_surface_has_no_triangles() {
+        let cell = march_cube(&PointN([0, 0, 0]), |_| (1.0, ()));
+
+        assert!(cell.is_none());
+    }
+
+    #[test]
+    fn cell_entirely_inside_surface_has_no_triangles() {
+        let cell = march_cube(&PointN([0, 0, 0]), |_| (-1.0, ()));
+
+        assert!(cell.is_none());
+    }
+
+    #[test]
+    fn cell_straddling_surface_on_one_axis_produces_triangles() {
+        // Corners 0-3 (z=0 face) are outside, corners 4-7 (z=1 face) are inside, so the surface
+        // crosses the 4 edges that connect them, right through the cell's midplane.
+        let cell = march_cube(&PointN([0, 0, 0]), |p| {
+            (if p.z() == 0 { 1.0 } else { -1.0 }, ())
+        })
+        .expect("cell straddles the isosurface");
+
+        assert!(!cell.triangles.is_empty());
+        for tri in &cell.triangles {
+            for vertex in tri {
+                assert!((vertex.position.z() - 0.5).abs() < std::f32::EPSILON);
+                assert!((vertex.t - 0.5).abs() < std::f32::EPSILON);
+            }
+        }
+    }
+}
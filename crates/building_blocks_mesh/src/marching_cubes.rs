@@ -0,0 +1,96 @@
+//! The [marching cubes](https://en.wikipedia.org/wiki/Marching_cubes) isosurface extraction
+//! algorithm, as an alternative to `surface_nets` when a sharper, classically-triangulated mesh
+//! is preferred.
+
+use crate::cube_march::{march_cube, CUBE_CORNERS, EDGE_CORNERS};
+use crate::PosNormMesh;
+
+use building_blocks_core::prelude::*;
+use building_blocks_storage::prelude::*;
+
+/// The output buffer used by `marching_cubes`. Reuse this to avoid reallocating the mesh data on
+/// every call.
+#[derive(Default)]
+pub struct MarchingCubesBuffer {
+    pub mesh: PosNormMesh,
+}
+
+impl MarchingCubesBuffer {
+    pub fn reset(&mut self) {
+        self.mesh.positions.clear();
+        self.mesh.normals.clear();
+        self.mesh.indices.clear();
+    }
+}
+
+/// Runs the marching cubes algorithm over `sdf`, restricted to `extent`, appending the resulting
+/// triangle mesh to `output`. Unlike `surface_nets`, `extent` must include a padding border of 2
+/// voxels (not 1) on each side, and only the cells whose minimum corner lies in
+/// `extent.padded(-2)` are marched: 1 voxel so that every cell's corners can be sampled, and 1
+/// more so that a central-difference gradient can be computed at any of those corners, even the
+/// ones on the outermost shell of cells.
+pub fn marching_cubes(sdf: &Array3<f32>, extent: &Extent3i, output: &mut MarchingCubesBuffer) {
+    output.reset();
+
+    let cell_extent = extent.padded(-2);
+
+    for p in cell_extent.iter_points() {
+        march_cell(sdf, &p, &mut output.mesh);
+    }
+}
+
+fn march_cell(sdf: &Array3<f32>, p: &Point3i, mesh: &mut PosNormMesh) {
+    let cell = match march_cube(p, |q| (sdf.get(q), ())) {
+        Some(cell) => cell,
+        // Entirely inside or outside the surface; no triangles.
+        None => return,
+    };
+
+    // The field gradient at each of the cell's 8 corners, computed once per cell rather than
+    // once per vertex.
+    let corner_gradients: Vec<Point3f> = CUBE_CORNERS
+        .iter()
+        .map(|offset| gradient(sdf, &(*p + *offset)))
+        .collect();
+
+    for triangle in cell.triangles {
+        let base_index = mesh.positions.len();
+        for vertex in &triangle {
+            // A true per-vertex normal, blended between the edge's two corner gradients by the
+            // same `t` used to interpolate its position, so adjacent triangles shade smoothly
+            // instead of each vertex snapping to one of only two flat-faceted normals.
+            let (c0, c1) = EDGE_CORNERS[vertex.edge];
+            let normal = normalize(lerp(corner_gradients[c0], corner_gradients[c1], vertex.t));
+
+            mesh.positions.push([
+                vertex.position.x(),
+                vertex.position.y(),
+                vertex.position.z(),
+            ]);
+            mesh.normals.push([normal.x(), normal.y(), normal.z()]);
+        }
+        mesh.indices
+            .extend([base_index, base_index + 1, base_index + 2]);
+    }
+}
+
+fn lerp(a: Point3f, b: Point3f, t: f32) -> Point3f {
+    a + (b - a).scalar_mul(t)
+}
+
+fn normalize(v: Point3f) -> Point3f {
+    let norm = v.norm();
+    if norm > std::f32::EPSILON {
+        v.scalar_mul(1.0 / norm)
+    } else {
+        PointN([0.0, 1.0, 0.0])
+    }
+}
+
+fn gradient(sdf: &Array3<f32>, p: &Point3i) -> Point3f {
+    let dx = sdf.get(*p + PointN([1, 0, 0])) - sdf.get(*p + PointN([-1, 0, 0]));
+    let dy = sdf.get(*p + PointN([0, 1, 0])) - sdf.get(*p + PointN([0, -1, 0]));
+    let dz = sdf.get(*p + PointN([0, 0, 1])) - sdf.get(*p + PointN([0, 0, -1]));
+
+    PointN([dx, dy, dz])
+}
@@ -0,0 +1,111 @@
+//! A multi-material variant of `marching_cubes`, carrying a per-vertex material ID alongside
+//! position and normal, so voxel chunks can mesh more than one block/material at a time instead
+//! of a single monocolor `PosNormMesh`.
+
+use crate::cube_march::{march_cube, ISO_LEVEL};
+
+use building_blocks_core::prelude::*;
+use building_blocks_storage::prelude::*;
+
+/// Like `PosNormMesh`, but with an extra per-vertex material ID.
+#[derive(Default)]
+pub struct PosNormMatMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub materials: Vec<u8>,
+    pub indices: Vec<usize>,
+}
+
+/// The output buffer used by `marching_cubes_material`. Reuse this to avoid reallocating the mesh
+/// data on every call.
+#[derive(Default)]
+pub struct MaterialMarchingCubesBuffer {
+    pub mesh: PosNormMatMesh,
+}
+
+impl MaterialMarchingCubesBuffer {
+    pub fn reset(&mut self) {
+        self.mesh.positions.clear();
+        self.mesh.normals.clear();
+        self.mesh.materials.clear();
+        self.mesh.indices.clear();
+    }
+}
+
+/// Like `marching_cubes::marching_cubes`, but each voxel of `sdf` carries a `(distance,
+/// material)` pair instead of a bare distance. Every emitted vertex is tagged with the material
+/// of whichever solid corner of its cell is nearest to the surface.
+pub fn marching_cubes_material(
+    sdf: &Array3<(f32, u8)>,
+    extent: &Extent3i,
+    output: &mut MaterialMarchingCubesBuffer,
+) {
+    output.reset();
+
+    let cell_extent = extent.padded(-1);
+
+    for p in cell_extent.iter_points() {
+        march_cell(sdf, &p, &mut output.mesh);
+    }
+}
+
+fn march_cell(sdf: &Array3<(f32, u8)>, p: &Point3i, mesh: &mut PosNormMatMesh) {
+    let cell = match march_cube(p, |q| sdf.get(q)) {
+        Some(cell) => cell,
+        // Entirely inside or outside the surface; no triangles.
+        None => return,
+    };
+
+    let material = nearest_solid_material(&cell.corner_values, &cell.corner_attrs);
+
+    for triangle in cell.triangles {
+        let positions = [
+            triangle[0].position,
+            triangle[1].position,
+            triangle[2].position,
+        ];
+        let normal = face_normal(&positions);
+
+        let base_index = mesh.positions.len();
+        for pos in &positions {
+            mesh.positions.push([pos.x(), pos.y(), pos.z()]);
+            mesh.normals.push(normal);
+            mesh.materials.push(material);
+        }
+        mesh.indices
+            .extend([base_index, base_index + 1, base_index + 2]);
+    }
+}
+
+fn nearest_solid_material(corner_values: &[f32; 8], corner_materials: &[u8; 8]) -> u8 {
+    let mut nearest: Option<(f32, u8)> = None;
+    for i in 0..8 {
+        let d = corner_values[i];
+        if d < ISO_LEVEL && nearest.map_or(true, |(nd, _)| d.abs() < nd) {
+            nearest = Some((d.abs(), corner_materials[i]));
+        }
+    }
+
+    nearest.map_or(0, |(_, m)| m)
+}
+
+fn face_normal(positions: &[Point3f]) -> [f32; 3] {
+    // A flat per-triangle normal from the edge vectors is cheap and indistinguishable from a
+    // gradient-based normal at the facet sizes marching cubes produces. The lookup tables wind
+    // each triangle counter-clockwise as seen from the solid side, so `e2 x e1` (not `e1 x e2`)
+    // is the one that points out of the solid, like the gradient.
+    let e1 = positions[1] - positions[0];
+    let e2 = positions[2] - positions[0];
+    let n = PointN([
+        e2.y() * e1.z() - e2.z() * e1.y(),
+        e2.z() * e1.x() - e2.x() * e1.z(),
+        e2.x() * e1.y() - e2.y() * e1.x(),
+    ]);
+
+    let len = n.norm();
+    if len > std::f32::EPSILON {
+        [n.x() / len, n.y() / len, n.z() / len]
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
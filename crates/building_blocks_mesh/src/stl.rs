@@ -0,0 +1,61 @@
+//! Binary [STL](https://en.wikipedia.org/wiki/STL_(file_format)) export for meshes produced by
+//! `surface_nets` or `marching_cubes`, so generated shapes can be handed off to a slicer.
+
+use crate::PosNormMesh;
+
+use std::io;
+use std::io::Write;
+
+/// Writes `mesh` to `writer` using the binary STL layout: an 80-byte (ignored) header, a `u32`
+/// triangle count, then one record per triangle of (face normal, 3 vertex positions, `u16`
+/// attribute byte count), with the normal and positions as little-endian `f32`s.
+pub fn write_binary_stl(mesh: &PosNormMesh, mut writer: impl Write) -> io::Result<()> {
+    let num_triangles = mesh.indices.len() / 3;
+
+    writer.write_all(&[0u8; 80])?;
+    writer.write_all(&(num_triangles as u32).to_le_bytes())?;
+
+    for tri in mesh.indices.chunks(3) {
+        let positions = [
+            mesh.positions[tri[0]],
+            mesh.positions[tri[1]],
+            mesh.positions[tri[2]],
+        ];
+        let normal = face_normal(&mesh, tri);
+
+        write_vec3(&mut writer, normal)?;
+        for p in &positions {
+            write_vec3(&mut writer, *p)?;
+        }
+        writer.write_all(&0u16.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn face_normal(mesh: &PosNormMesh, tri: &[usize]) -> [f32; 3] {
+    // STL only stores one normal per facet, so just average the mesh's per-vertex normals rather
+    // than recomputing from the triangle's geometry.
+    let [n0, n1, n2] = [
+        mesh.normals[tri[0]],
+        mesh.normals[tri[1]],
+        mesh.normals[tri[2]],
+    ];
+
+    let sum = [n0[0] + n1[0] + n2[0], n0[1] + n1[1] + n2[1], n0[2] + n1[2] + n2[2]];
+    let len = (sum[0] * sum[0] + sum[1] * sum[1] + sum[2] * sum[2]).sqrt();
+
+    if len > std::f32::EPSILON {
+        [sum[0] / len, sum[1] / len, sum[2] / len]
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+fn write_vec3(writer: &mut impl Write, v: [f32; 3]) -> io::Result<()> {
+    for c in &v {
+        writer.write_all(&c.to_le_bytes())?;
+    }
+
+    Ok(())
+}
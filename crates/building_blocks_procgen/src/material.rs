@@ -0,0 +1,77 @@
+//! A material-aware variant of the SDF combinators in `signed_distance_fields`, for building
+//! compound shapes where each primitive contributes its own material/block ID at the surface.
+
+use building_blocks_core::prelude::*;
+
+/// A signed distance paired with the ID of the material that contributed it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfMaterial {
+    pub distance: f32,
+    pub material: u8,
+}
+
+/// Tags every sample of a plain SDF with a fixed `material` ID.
+pub fn with_material(
+    sdf: impl Fn(&Point3i) -> f32,
+    material: u8,
+) -> impl Fn(&Point3i) -> SdfMaterial {
+    move |p| SdfMaterial {
+        distance: sdf(p),
+        material,
+    }
+}
+
+/// Like `signed_distance_fields::union`, but keeps the material of whichever shape is closer.
+pub fn material_union(
+    a: impl Fn(&Point3i) -> SdfMaterial,
+    b: impl Fn(&Point3i) -> SdfMaterial,
+) -> impl Fn(&Point3i) -> SdfMaterial {
+    move |p| {
+        let (a, b) = (a(p), b(p));
+
+        if a.distance <= b.distance {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+/// Like `signed_distance_fields::intersection`, but keeps the material of whichever shape is
+/// farther (i.e. the one that bounds the intersected surface).
+pub fn material_intersection(
+    a: impl Fn(&Point3i) -> SdfMaterial,
+    b: impl Fn(&Point3i) -> SdfMaterial,
+) -> impl Fn(&Point3i) -> SdfMaterial {
+    move |p| {
+        let (a, b) = (a(p), b(p));
+
+        if a.distance >= b.distance {
+            a
+        } else {
+            b
+        }
+    }
+}
+
+/// Like `signed_distance_fields::difference`, but keeps the material of `a` unless `b` carves
+/// into it, in which case the (negated) surface of `b` takes over.
+pub fn material_difference(
+    a: impl Fn(&Point3i) -> SdfMaterial,
+    b: impl Fn(&Point3i) -> SdfMaterial,
+) -> impl Fn(&Point3i) -> SdfMaterial {
+    move |p| {
+        let a = a(p);
+        let b = b(p);
+        let neg_b = SdfMaterial {
+            distance: -b.distance,
+            material: b.material,
+        };
+
+        if a.distance >= neg_b.distance {
+            a
+        } else {
+            neg_b
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Noise-driven terrain generators built on top of a gradient noise primitive, for use as
+//! heightmaps or density SDFs in place of a hand-written wave function.
+
+use building_blocks_core::prelude::*;
+
+use noise::{NoiseFn, Perlin, Seedable};
+
+/// Parameters shared by the fBm-based generators below.
+#[derive(Clone, Copy, Debug)]
+pub struct FbmConfig {
+    pub seed: u32,
+    pub octaves: u32,
+    /// Frequency multiplier applied to each successive octave. Typically around `2.0`.
+    pub lacunarity: f32,
+    /// Amplitude multiplier applied to each successive octave. Typically around `0.5`.
+    pub gain: f32,
+    pub frequency: f32,
+    /// When `true`, sums `abs()` of each octave instead of the signed value, producing the
+    /// ridged, warped look commonly called "turbulence".
+    pub turbulence: bool,
+}
+
+impl Default for FbmConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+            frequency: 0.02,
+            turbulence: false,
+        }
+    }
+}
+
+fn fbm_2d(noise: &Perlin, config: &FbmConfig, x: f64, y: f64) -> f32 {
+    let mut amplitude = 1.0;
+    let mut frequency = config.frequency as f64;
+    let mut sum = 0.0;
+
+    for _ in 0..config.octaves {
+        let n = noise.get([x * frequency, y * frequency]) as f32;
+        sum += amplitude * if config.turbulence { n.abs() } else { n };
+        frequency *= config.lacunarity as f64;
+        amplitude *= config.gain;
+    }
+
+    sum
+}
+
+fn fbm_3d(noise: &Perlin, config: &FbmConfig, x: f64, y: f64, z: f64) -> f32 {
+    let mut amplitude = 1.0;
+    let mut frequency = config.frequency as f64;
+    let mut sum = 0.0;
+
+    for _ in 0..config.octaves {
+        let n = noise.get([x * frequency, y * frequency, z * frequency]) as f32;
+        sum += amplitude * if config.turbulence { n.abs() } else { n };
+        frequency *= config.lacunarity as f64;
+        amplitude *= config.gain;
+    }
+
+    sum
+}
+
+/// Builds an fBm heightmap generator, optionally domain-warped by a second, lower-frequency noise
+/// call (`warp_amplitude > 0.0`).
+pub fn fbm_height_map(
+    config: FbmConfig,
+    amplitude: f32,
+    warp_amplitude: f32,
+) -> impl Fn(&Point2i) -> f32 {
+    let noise = Perlin::new().set_seed(config.seed);
+    let warp_noise = Perlin::new().set_seed(config.seed.wrapping_add(1));
+    let warp_config = FbmConfig {
+        octaves: 2,
+        frequency: config.frequency * 0.25,
+        ..config
+    };
+
+    move |p| {
+        let (mut x, mut y) = (p.x() as f64, p.y() as f64);
+
+        if warp_amplitude > 0.0 {
+            let warp = (warp_amplitude * fbm_2d(&warp_noise, &warp_config, x, y)) as f64;
+            x += warp;
+            y += warp;
+        }
+
+        amplitude * fbm_2d(&noise, &config, x, y)
+    }
+}
+
+/// Builds an fBm density SDF generator over all 3 dimensions: negative where `amplitude` times
+/// the summed noise exceeds `threshold` (solid), positive elsewhere (air), suitable for feeding into
+/// `surface_nets` or `marching_cubes` to produce caves, overhangs, and other volumetric terrain
+/// that a 2D heightmap can't represent.
+pub fn fbm_density_sdf(
+    config: FbmConfig,
+    amplitude: f32,
+    warp_amplitude: f32,
+    threshold: f32,
+) -> impl Fn(&Point3i) -> f32 {
+    let noise = Perlin::new().set_seed(config.seed);
+    let warp_noise = Perlin::new().set_seed(config.seed.wrapping_add(1));
+    let warp_config = FbmConfig {
+        octaves: 2,
+        frequency: config.frequency * 0.25,
+        ..config
+    };
+
+    move |p| {
+        let (mut x, mut y, mut z) = (p.x() as f64, p.y() as f64, p.z() as f64);
+
+        if warp_amplitude > 0.0 {
+            let warp = warp_amplitude * fbm_3d(&warp_noise, &warp_config, x, y, z);
+            x += warp as f64;
+            y += warp as f64;
+            z += warp as f64;
+        }
+
+        threshold - amplitude * fbm_3d(&noise, &config, x, y, z)
+    }
+}
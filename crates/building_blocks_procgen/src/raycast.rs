@@ -0,0 +1,67 @@
+//! [Sphere tracing](https://en.wikipedia.org/wiki/Ray_marching#Sphere_tracing) against the SDFs
+//! produced by `signed_distance_fields`, e.g. for mouse picking or collision queries.
+
+use building_blocks_core::prelude::*;
+
+const MAX_ITERATIONS: u32 = 128;
+
+/// The result of a successful `raymarch` call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub point: Point3f,
+    pub distance: f32,
+    pub normal: Point3f,
+}
+
+/// Sphere-traces `sdf` along the ray from `origin` in `direction` (expected to be normalized),
+/// stepping by the SDF's distance estimate at each iteration. Returns the first surface hit
+/// within `max_distance`, or `None` if the ray escapes or the iteration cap is reached first.
+pub fn raymarch(
+    sdf: impl Fn(&Point3f) -> f32,
+    origin: Point3f,
+    direction: Point3f,
+    max_distance: f32,
+    epsilon: f32,
+) -> Option<RaycastHit> {
+    let mut t = 0.0;
+
+    for _ in 0..MAX_ITERATIONS {
+        let p = origin + direction.scalar_mul(t);
+        let d = sdf(&p);
+
+        if d < epsilon {
+            return Some(RaycastHit {
+                point: p,
+                distance: t,
+                normal: estimate_normal(&sdf, &p, epsilon),
+            });
+        }
+
+        t += d;
+
+        if t > max_distance {
+            break;
+        }
+    }
+
+    None
+}
+
+fn estimate_normal(sdf: impl Fn(&Point3f) -> f32, p: &Point3f, epsilon: f32) -> Point3f {
+    let ex = PointN([epsilon, 0.0, 0.0]);
+    let ey = PointN([0.0, epsilon, 0.0]);
+    let ez = PointN([0.0, 0.0, epsilon]);
+
+    let gradient = PointN([
+        sdf(&(*p + ex)) - sdf(&(*p - ex)),
+        sdf(&(*p + ey)) - sdf(&(*p - ey)),
+        sdf(&(*p + ez)) - sdf(&(*p - ez)),
+    ]);
+
+    let norm = gradient.norm();
+    if norm > std::f32::EPSILON {
+        gradient.scalar_mul(1.0 / norm)
+    } else {
+        PointN([0.0, 1.0, 0.0])
+    }
+}
@@ -40,3 +40,183 @@ pub fn torus(t: Point2f) -> impl Fn(&Point3i) -> f32 {
         q.norm() - t.y()
     }
 }
+
+/// Combines two SDFs by taking the minimum distance, i.e. the union of the two solids.
+pub fn union(
+    a: impl Fn(&Point3i) -> f32,
+    b: impl Fn(&Point3i) -> f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| a(p).min(b(p))
+}
+
+/// Combines two SDFs by taking the maximum distance, i.e. the intersection of the two solids.
+pub fn intersection(
+    a: impl Fn(&Point3i) -> f32,
+    b: impl Fn(&Point3i) -> f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| a(p).max(b(p))
+}
+
+/// Subtracts the solid of `b` from the solid of `a`.
+pub fn difference(
+    a: impl Fn(&Point3i) -> f32,
+    b: impl Fn(&Point3i) -> f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| a(p).max(-b(p))
+}
+
+/// Wraps `sdf` so that it's sampled in a transformed coordinate space, letting a primitive
+/// defined at the origin be placed and oriented anywhere. `inverse_rotation` should map a world
+/// direction back into `sdf`'s local space (e.g. the inverse of whatever rotation you applied),
+/// `translation` is the object's position in world space, and `scale` is a uniform scale factor.
+/// The query point is mapped into local space before sampling, and the result is multiplied by
+/// `scale` to keep it a valid Euclidean distance.
+pub fn transform(
+    sdf: impl Fn(&Point3i) -> f32,
+    inverse_rotation: impl Fn(Point3f) -> Point3f,
+    translation: Point3f,
+    scale: f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| {
+        let pf: Point3f = (*p).into();
+        let local = inverse_rotation((pf - translation).scalar_mul(1.0 / scale));
+        let local = PointN([
+            local.x().round() as i32,
+            local.y().round() as i32,
+            local.z().round() as i32,
+        ]);
+
+        scale * sdf(&local)
+    }
+}
+
+/// Wraps `sdf` so that it tiles infinitely across space with the given `period` along each axis.
+pub fn repeat(sdf: impl Fn(&Point3i) -> f32, period: Point3f) -> impl Fn(&Point3i) -> f32 {
+    move |p| {
+        let pf: Point3f = (*p).into();
+        let q = PointN([
+            pf.x() - period.x() * (pf.x() / period.x()).round(),
+            pf.y() - period.y() * (pf.y() / period.y()).round(),
+            pf.z() - period.z() * (pf.z() / period.z()).round(),
+        ]);
+        let q = PointN([q.x().round() as i32, q.y().round() as i32, q.z().round() as i32]);
+
+        sdf(&q)
+    }
+}
+
+/// Wraps `sdf` so that it's reflected across the origin along any axis where the corresponding
+/// flag is `true`, turning a one-sided shape into a symmetric one.
+pub fn mirror(sdf: impl Fn(&Point3i) -> f32, axes: [bool; 3]) -> impl Fn(&Point3i) -> f32 {
+    move |p| {
+        let q = PointN([
+            if axes[0] { p.x().abs() } else { p.x() },
+            if axes[1] { p.y().abs() } else { p.y() },
+            if axes[2] { p.z().abs() } else { p.z() },
+        ]);
+
+        sdf(&q)
+    }
+}
+
+fn mix(x: f32, y: f32, t: f32) -> f32 {
+    x * (1.0 - t) + y * t
+}
+
+/// Like `union`, but blends the two solids together smoothly within a radius `k` of their
+/// surfaces, instead of leaving a sharp seam.
+pub fn smooth_union(
+    a: impl Fn(&Point3i) -> f32,
+    b: impl Fn(&Point3i) -> f32,
+    k: f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| {
+        let (a, b) = (a(p), b(p));
+        let h = (0.5 + 0.5 * (b - a) / k).max(0.0).min(1.0);
+
+        mix(b, a, h) - k * h * (1.0 - h)
+    }
+}
+
+/// Like `intersection`, but blends the two solids together smoothly within a radius `k` of their
+/// surfaces, instead of leaving a sharp seam.
+pub fn smooth_intersection(
+    a: impl Fn(&Point3i) -> f32,
+    b: impl Fn(&Point3i) -> f32,
+    k: f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| {
+        let (a, b) = (a(p), b(p));
+        let h = (0.5 - 0.5 * (b - a) / k).max(0.0).min(1.0);
+
+        mix(b, a, h) + k * h * (1.0 - h)
+    }
+}
+
+/// Like `difference`, but blends the two solids together smoothly within a radius `k` of their
+/// surfaces, instead of leaving a sharp seam.
+pub fn smooth_difference(
+    a: impl Fn(&Point3i) -> f32,
+    b: impl Fn(&Point3i) -> f32,
+    k: f32,
+) -> impl Fn(&Point3i) -> f32 {
+    move |p| {
+        let (a, nb) = (a(p), -b(p));
+        let h = (0.5 - 0.5 * (nb - a) / k).max(0.0).min(1.0);
+
+        mix(nb, a, h) + k * h * (1.0 - h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two non-overlapping unit spheres, far enough apart that their distances at the origin are
+    // easy to reason about by hand: -1.0 (inside sphere_a) and 9.0 (9 units outside sphere_b).
+    fn sphere_a() -> impl Fn(&Point3i) -> f32 {
+        sphere(PointN([0.0, 0.0, 0.0]), 1.0)
+    }
+
+    fn sphere_b() -> impl Fn(&Point3i) -> f32 {
+        sphere(PointN([10.0, 0.0, 0.0]), 1.0)
+    }
+
+    const ORIGIN: Point3i = PointN([0, 0, 0]);
+
+    #[test]
+    fn union_takes_the_minimum_distance() {
+        assert_eq!(union(sphere_a(), sphere_b())(&ORIGIN), -1.0);
+    }
+
+    #[test]
+    fn intersection_takes_the_maximum_distance() {
+        assert_eq!(intersection(sphere_a(), sphere_b())(&ORIGIN), 9.0);
+    }
+
+    #[test]
+    fn difference_subtracts_b_from_a() {
+        assert_eq!(difference(sphere_a(), sphere_b())(&ORIGIN), -1.0);
+    }
+
+    #[test]
+    fn smooth_union_matches_sharp_union_far_from_the_blend_radius() {
+        let sharp = union(sphere_a(), sphere_b())(&ORIGIN);
+        let smooth = smooth_union(sphere_a(), sphere_b(), 0.1)(&ORIGIN);
+
+        assert!((smooth - sharp).abs() < 1e-4);
+    }
+
+    #[test]
+    fn smooth_union_rounds_off_the_corner_at_the_midpoint() {
+        // At the midpoint both spheres are equidistant, so the blend weight h is exactly 0.5 and
+        // the `- k*h*(1-h)` correction term is at its most negative (-k/4): the smoothed surface
+        // bulges past the sharp union's corner by exactly that amount.
+        let midpoint = PointN([5, 0, 0]);
+        let k = 4.0;
+        let sharp_min = union(sphere_a(), sphere_b())(&midpoint);
+        let smooth = smooth_union(sphere_a(), sphere_b(), k)(&midpoint);
+
+        assert!((smooth - (sharp_min - k / 4.0)).abs() < 1e-4);
+    }
+}
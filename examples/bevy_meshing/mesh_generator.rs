@@ -1,5 +1,11 @@
 use building_blocks::core::prelude::*;
-use building_blocks::mesh::{height_map::*, surface_nets::*, PosNormMesh};
+use building_blocks::mesh::{
+    height_map::*, marching_cubes::*, material_mesh::*, stl::write_binary_stl, surface_nets::*,
+    PosNormMesh,
+};
+use building_blocks::procgen::material::*;
+use building_blocks::procgen::noise::*;
+use building_blocks::procgen::raycast::*;
 use building_blocks::procgen::signed_distance_fields::*;
 use building_blocks::storage::prelude::*;
 
@@ -8,21 +14,34 @@ use bevy::{
     render::{mesh::VertexAttribute, pipeline::PrimitiveTopology},
 };
 
+use std::fs::File;
+
 pub struct MeshGeneratorState {
     current_shape_index: i32,
     chunk_mesh_entities: Vec<Entity>,
+    // Whether Sdf shapes mesh with `marching_cubes` instead of the default `surface_nets`.
+    use_marching_cubes: bool,
+    // The whole current shape's mesh, stitched together from every chunk, so it can be exported
+    // as one STL file instead of just the last chunk generated.
+    exportable_mesh: PosNormMesh,
 
     // reused to avoid reallocations
     surface_nets_buffer: SurfaceNetsBuffer,
+    marching_cubes_buffer: MarchingCubesBuffer,
     height_map_mesh_buffer: HeightMapMeshBuffer,
+    material_marching_cubes_buffer: MaterialMarchingCubesBuffer,
 }
 
 impl MeshGeneratorState {
     pub fn new() -> Self {
         Self {
             current_shape_index: 0,
+            use_marching_cubes: false,
+            exportable_mesh: PosNormMesh::default(),
             height_map_mesh_buffer: HeightMapMeshBuffer::default(),
             surface_nets_buffer: SurfaceNetsBuffer::default(),
+            marching_cubes_buffer: MarchingCubesBuffer::default(),
+            material_marching_cubes_buffer: MaterialMarchingCubesBuffer::default(),
             chunk_mesh_entities: Vec::new(),
         }
     }
@@ -32,6 +51,7 @@ impl MeshGeneratorState {
 enum Shape {
     Sdf(Sdf),
     HeightMap(HeightMap),
+    MultiMaterialSdf(MultiMaterialSdf),
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -40,6 +60,10 @@ enum Sdf {
     Plane,
     Sphere,
     Torus,
+    UnionSphereCube,
+    SmoothUnionSphereTorus,
+    RotatedCube,
+    TiledMirroredSpheres,
 }
 
 impl Sdf {
@@ -49,6 +73,41 @@ impl Sdf {
             Sdf::Plane => Box::new(plane(PointN([0.5, 0.5, 0.5]), 1.0)),
             Sdf::Sphere => Box::new(sphere(PointN([0.0, 0.0, 0.0]), 35.0)),
             Sdf::Torus => Box::new(torus(PointN([35.0, 10.0]))),
+            // A sharp-seamed CSG union of two overlapping primitives.
+            Sdf::UnionSphereCube => Box::new(union(
+                sphere(PointN([-15.0, 0.0, 0.0]), 25.0),
+                cube(PointN([15.0, 0.0, 0.0]), 20.0),
+            )),
+            // A sphere and a torus, blended smoothly where they overlap.
+            Sdf::SmoothUnionSphereTorus => Box::new(smooth_union(
+                sphere(PointN([0.0, -15.0, 0.0]), 20.0),
+                torus(PointN([35.0, 10.0])),
+                10.0,
+            )),
+            // A cube rotated 45 degrees about the Y axis via `transform`.
+            Sdf::RotatedCube => {
+                let angle: f32 = std::f32::consts::FRAC_PI_4;
+                let (sin, cos) = (angle.sin(), angle.cos());
+                // The inverse of a rotation by `angle` about the Y axis.
+                let inverse_rotation = move |p: Point3f| {
+                    PointN([cos * p.x() - sin * p.z(), p.y(), sin * p.x() + cos * p.z()])
+                };
+
+                Box::new(transform(
+                    cube(PointN([0.0, 0.0, 0.0]), 25.0),
+                    inverse_rotation,
+                    PointN([0.0, 0.0, 0.0]),
+                    1.0,
+                ))
+            }
+            // A single sphere tiled infinitely and then mirrored across the X and Z axes.
+            Sdf::TiledMirroredSpheres => Box::new(mirror(
+                repeat(
+                    sphere(PointN([0.0, 0.0, 0.0]), 10.0),
+                    PointN([40.0, 40.0, 40.0]),
+                ),
+                [true, false, true],
+            )),
         }
     }
 }
@@ -56,19 +115,57 @@ impl Sdf {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum HeightMap {
     Wave,
+    NoiseTerrain,
 }
 
 impl HeightMap {
-    fn get_height_map(&self) -> impl Fn(&Point2i) -> f32 {
+    fn get_height_map(&self) -> Box<dyn Fn(&Point2i) -> f32> {
         match self {
-            HeightMap::Wave => {
-                |p: &Point2i| 10.0 * (1.0 + (0.1 * p.x() as f32).cos() + (0.1 * p.y() as f32).sin())
+            HeightMap::Wave => Box::new(|p: &Point2i| {
+                10.0 * (1.0 + (0.1 * p.x() as f32).cos() + (0.1 * p.y() as f32).sin())
+            }),
+            HeightMap::NoiseTerrain => Box::new(fbm_height_map(
+                FbmConfig {
+                    seed: 1,
+                    octaves: 4,
+                    lacunarity: 2.0,
+                    gain: 0.5,
+                    frequency: 0.02,
+                    turbulence: true,
+                },
+                20.0,
+                4.0,
+            )),
+        }
+    }
+}
+
+/// A signed distance paired with a material ID, combined from two primitives each tagged with
+/// their own material so the mesher can carry block/material IDs through to the vertex data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MultiMaterialSdf {
+    SphereAndCube,
+}
+
+impl MultiMaterialSdf {
+    fn get_sdf(&self) -> Box<dyn Fn(&Point3i) -> (f32, u8)> {
+        match self {
+            MultiMaterialSdf::SphereAndCube => {
+                let sphere_mat = with_material(sphere(PointN([-15.0, 0.0, 0.0]), 25.0), 0);
+                let cube_mat = with_material(cube(PointN([15.0, 0.0, 0.0]), 20.0), 1);
+                let combined = material_union(sphere_mat, cube_mat);
+
+                Box::new(move |p| {
+                    let m = combined(p);
+
+                    (m.distance, m.material)
+                })
             }
         }
     }
 }
 
-const NUM_SHAPES: i32 = 5;
+const NUM_SHAPES: i32 = 11;
 
 fn choose_shape(index: i32) -> Shape {
     match index {
@@ -76,7 +173,13 @@ fn choose_shape(index: i32) -> Shape {
         1 => Shape::Sdf(Sdf::Plane),
         2 => Shape::Sdf(Sdf::Sphere),
         3 => Shape::Sdf(Sdf::Torus),
-        4 => Shape::HeightMap(HeightMap::Wave),
+        4 => Shape::Sdf(Sdf::UnionSphereCube),
+        5 => Shape::Sdf(Sdf::SmoothUnionSphereTorus),
+        6 => Shape::Sdf(Sdf::RotatedCube),
+        7 => Shape::Sdf(Sdf::TiledMirroredSpheres),
+        8 => Shape::HeightMap(HeightMap::Wave),
+        9 => Shape::HeightMap(HeightMap::NoiseTerrain),
+        10 => Shape::MultiMaterialSdf(MultiMaterialSdf::SphereAndCube),
         _ => panic!("bad shape index"),
     }
 }
@@ -100,6 +203,19 @@ pub fn mesh_generator_system(
         state.current_shape_index = (state.current_shape_index + 1).rem_euclid(NUM_SHAPES);
     }
 
+    if keyboard_input.just_pressed(KeyCode::M) {
+        new_shape_requested = true;
+        state.use_marching_cubes = !state.use_marching_cubes;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::X) {
+        export_mesh_to_stl(&state.exportable_mesh);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::R) {
+        pick_surface(choose_shape(state.current_shape_index));
+    }
+
     if new_shape_requested || state.chunk_mesh_entities.is_empty() {
         // Delete the old meshes.
         for entity in state.chunk_mesh_entities.drain(..) {
@@ -123,6 +239,13 @@ pub fn mesh_generator_system(
                 material.0,
                 &mut meshes,
             ),
+            Shape::MultiMaterialSdf(mm) => generate_chunk_meshes_from_multi_material_sdf(
+                mm,
+                &mut state,
+                &mut commands,
+                material.0,
+                &mut meshes,
+            ),
         }
     }
 }
@@ -151,27 +274,123 @@ fn generate_chunk_meshes_from_sdf(
     copy_extent(&sample_extent, &sdf, &mut map);
 
     // Generate the chunk meshes.
+    state.exportable_mesh = PosNormMesh::default();
     let local_cache = LocalChunkCache::new();
     let map_reader = ChunkMapReader3::new(&map, &local_cache);
     for chunk_key in map.chunk_keys() {
-        let padded_chunk_extent = map.extent_for_chunk_at_key(chunk_key).padded(1);
+        // `marching_cubes` needs one more voxel of padding than `surface_nets` does, to compute
+        // gradients at cell corners on the outermost shell.
+        let padding = if state.use_marching_cubes { 2 } else { 1 };
+        let padded_chunk_extent = map.extent_for_chunk_at_key(chunk_key).padded(padding);
         let mut padded_chunk = Array3::fill(padded_chunk_extent, 0.0);
         copy_extent(&padded_chunk_extent, &map_reader, &mut padded_chunk);
-        surface_nets(
+
+        let chunk_mesh = if state.use_marching_cubes {
+            marching_cubes(
+                &padded_chunk,
+                &padded_chunk_extent,
+                &mut state.marching_cubes_buffer,
+            );
+
+            &state.marching_cubes_buffer.mesh
+        } else {
+            surface_nets(
+                &padded_chunk,
+                &padded_chunk_extent,
+                &mut state.surface_nets_buffer,
+            );
+
+            &state.surface_nets_buffer.mesh
+        };
+
+        if chunk_mesh.indices.is_empty() {
+            continue;
+        }
+
+        append_mesh(&mut state.exportable_mesh, chunk_mesh);
+        state.chunk_mesh_entities.push(create_mesh_entity(
+            chunk_mesh, commands, material, meshes, None,
+        ));
+    }
+}
+
+/// Appends `mesh`'s geometry onto the end of `into`, offsetting indices so they still point at
+/// the right (now-shifted) vertices.
+fn append_mesh(into: &mut PosNormMesh, mesh: &PosNormMesh) {
+    let base_index = into.positions.len();
+    into.positions.extend_from_slice(&mesh.positions);
+    into.normals.extend_from_slice(&mesh.normals);
+    into.indices
+        .extend(mesh.indices.iter().map(|i| base_index + i));
+}
+
+/// Writes `mesh` out as a binary STL file, e.g. for inspecting the current shape in a slicer.
+fn export_mesh_to_stl(mesh: &PosNormMesh) {
+    if mesh.indices.is_empty() {
+        return;
+    }
+
+    match File::create("mesh_generator_output.stl") {
+        Ok(file) => {
+            if let Err(e) = write_binary_stl(mesh, file) {
+                eprintln!("Failed to write mesh_generator_output.stl: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to create mesh_generator_output.stl: {}", e),
+    }
+}
+
+fn generate_chunk_meshes_from_multi_material_sdf(
+    sdf: MultiMaterialSdf,
+    state: &mut MeshGeneratorState,
+    commands: &mut Commands,
+    material: Handle<StandardMaterial>,
+    meshes: &mut Assets<Mesh>,
+) {
+    let sdf = sdf.get_sdf();
+    let sample_extent = Extent3i::from_min_and_shape(PointN([-50; 3]), PointN([100; 3]));
+    let chunk_shape = PointN([16; 3]);
+    let ambient_value = (std::f32::MAX, 0); // air
+    let default_chunk_meta = ();
+    let mut map = ChunkMap3::new(
+        chunk_shape,
+        ambient_value,
+        default_chunk_meta,
+        FastLz4 { level: 10 },
+    );
+    copy_extent(&sample_extent, &sdf, &mut map);
+
+    // Generate the chunk meshes.
+    state.exportable_mesh = PosNormMesh::default();
+    let local_cache = LocalChunkCache::new();
+    let map_reader = ChunkMapReader3::new(&map, &local_cache);
+    for chunk_key in map.chunk_keys() {
+        let padded_chunk_extent = map.extent_for_chunk_at_key(chunk_key).padded(1);
+        let mut padded_chunk = Array3::fill(padded_chunk_extent, (0.0, 0));
+        copy_extent(&padded_chunk_extent, &map_reader, &mut padded_chunk);
+        marching_cubes_material(
             &padded_chunk,
             &padded_chunk_extent,
-            &mut state.surface_nets_buffer,
+            &mut state.material_marching_cubes_buffer,
         );
 
-        if state.surface_nets_buffer.mesh.indices.is_empty() {
+        let mat_mesh = &state.material_marching_cubes_buffer.mesh;
+        if mat_mesh.indices.is_empty() {
             continue;
         }
 
+        let mesh = PosNormMesh {
+            positions: mat_mesh.positions.clone(),
+            normals: mat_mesh.normals.clone(),
+            indices: mat_mesh.indices.clone(),
+        };
+        append_mesh(&mut state.exportable_mesh, &mesh);
         state.chunk_mesh_entities.push(create_mesh_entity(
-            &state.surface_nets_buffer.mesh,
+            &mesh,
             commands,
             material,
             meshes,
+            Some(&mat_mesh.materials),
         ));
     }
 }
@@ -200,6 +419,7 @@ fn generate_chunk_meshes_from_height_map(
     copy_extent(&sample_extent, &height_map, &mut map);
 
     // Generate the chunk meshes.
+    state.exportable_mesh = PosNormMesh::default();
     let local_cache = LocalChunkCache::new();
     let map_reader = ChunkMapReader2::new(&map, &local_cache);
     for chunk_key in map.chunk_keys() {
@@ -224,28 +444,65 @@ fn generate_chunk_meshes_from_height_map(
             continue;
         }
 
+        append_mesh(&mut state.exportable_mesh, &state.height_map_mesh_buffer.mesh);
         state.chunk_mesh_entities.push(create_mesh_entity(
             &state.height_map_mesh_buffer.mesh,
             commands,
             material,
             meshes,
+            None,
         ));
     }
 }
 
+/// Sphere-traces a fixed ray down through the current shape, if it's an `Sdf`, and logs the
+/// surface hit. Stands in for mouse picking, which would just need a different ray per frame.
+fn pick_surface(shape: Shape) {
+    let sdf = match shape {
+        Shape::Sdf(sdf) => sdf.get_sdf(),
+        _ => return,
+    };
+    // `raymarch` samples a float-point field, so round the query point onto the integer grid
+    // that the `Sdf` closures are defined on.
+    let sdf_f = move |p: &Point3f| {
+        sdf(&PointN([
+            p.x().round() as i32,
+            p.y().round() as i32,
+            p.z().round() as i32,
+        ]))
+    };
+
+    let origin = PointN([0.0, 100.0, 0.0]);
+    let direction = PointN([0.0, -1.0, 0.0]);
+    match raymarch(sdf_f, origin, direction, 200.0, 0.01) {
+        Some(hit) => println!(
+            "picked surface at {:?} (distance {}, normal {:?})",
+            hit.point, hit.distance, hit.normal
+        ),
+        None => println!("ray missed the surface"),
+    }
+}
+
 fn create_mesh_entity(
     mesh: &PosNormMesh,
     commands: &mut Commands,
     material: Handle<StandardMaterial>,
     meshes: &mut Assets<Mesh>,
+    materials: Option<&[u8]>,
 ) -> Entity {
+    // UVs don't matter for these meshes, so we repurpose the U channel to carry a per-vertex
+    // material ID for multi-material meshes; monocolor meshes just get zeroes.
+    let uvs = match materials {
+        Some(materials) => materials.iter().map(|&m| [m as f32, 0.0]).collect(),
+        None => vec![[0.0; 2]; mesh.normals.len()],
+    };
+
     let mesh = meshes.add(Mesh {
         primitive_topology: PrimitiveTopology::TriangleList,
         attributes: vec![
             VertexAttribute::position(mesh.positions.clone()),
             VertexAttribute::normal(mesh.normals.clone()),
-            // UVs don't matter for this monocolor mesh
-            VertexAttribute::uv(vec![[0.0; 2]; mesh.normals.len()]),
+            VertexAttribute::uv(uvs),
         ],
         indices: Some(mesh.indices.iter().map(|i| *i as u32).collect()),
     });